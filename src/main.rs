@@ -1,12 +1,6 @@
-#![allow(arithmetic_overflow)]
 use serde::Serialize;
 use serde_with::serde_as;
-use std::{
-    collections::HashMap,
-    env, fs,
-    mem::{size_of, transmute, MaybeUninit},
-    ptr::copy_nonoverlapping,
-};
+use std::{collections::HashMap, env, fs, mem::size_of, process};
 
 const TS_BIN_VERSION_START_INDEX: usize = 5;
 const TS_BIN_VERSION_LEN: usize = 4;
@@ -26,6 +20,15 @@ const TS_CFG_BLOCK_RESERVED_LEN: usize = 9;
 
 const GOODIX_CFG_MAX_SIZE: usize = 4096;
 
+const GOODIX_BERLIN_PANEL_NAME_LEN: usize = 8;
+const GOODIX_BERLIN_FW_PID_LEN: usize = 8;
+const GOODIX_BERLIN_FW_VID_LEN: usize = 4;
+const GOODIX_BERLIN_HEAD_RESERVED_LEN: usize = 3;
+const GOODIX_BERLIN_CFG_HEAD_LEN: usize = size_of::<GoodixBerlinCfgHead>();
+const GOODIX_BERLIN_CHECKSUM_LEN: usize = 2;
+const GOODIX_BERLIN_CHECKSUM_OFFSET: usize = GOODIX_BERLIN_CFG_HEAD_LEN - GOODIX_BERLIN_CHECKSUM_LEN;
+const GOODIX_BERLIN_BLOCK_DESC_LEN: usize = size_of::<GoodixBerlinBlockDesc>();
+
 #[derive(Debug, Copy, Clone, Serialize)]
 #[repr(C, packed(1))]
 struct GoodixCfgPkgReg {
@@ -34,7 +37,7 @@ struct GoodixCfgPkgReg {
     reserved2: u8,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C, packed(1))]
 struct GoodixCfgPkgConstInfo {
     pkg_len: u32,
@@ -50,7 +53,7 @@ struct GoodixCfgPkgConstInfo {
     trigger_offset: u16,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C, packed(1))]
 struct GoodixCfgPkgRegInfo {
     cfg_send_flag: GoodixCfgPkgReg,
@@ -79,13 +82,13 @@ struct GoodixCfgBinHead {
     pkg_num: u8,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 struct GoodixCfgPackage {
     cnst_info: GoodixCfgPkgConstInfo,
     reg_info: GoodixCfgPkgRegInfo,
     #[serde(skip)]
-    cfg: *const u8,
+    cfg: Vec<u8>,
     pkg_len: u32,
 }
 
@@ -106,111 +109,966 @@ struct GoodixIcConfig {
     data: [u8; GOODIX_CFG_MAX_SIZE],
 }
 
+/// Header of the newer "Berlin"-generation `goodix_config_head`, used by the
+/// Berlin IC driver in place of the legacy `GoodixCfgBinHead` + package-table
+/// layout above. A single flat config blob rather than a table of per-sensor
+/// packages: a fixed head, a table of block descriptors, then the block
+/// payloads back to back.
+#[derive(Debug, Serialize)]
+#[repr(C, packed(1))]
+struct GoodixBerlinCfgHead {
+    panel_name: [u8; GOODIX_BERLIN_PANEL_NAME_LEN],
+    fw_pid: [u8; GOODIX_BERLIN_FW_PID_LEN],
+    fw_vid: [u8; GOODIX_BERLIN_FW_VID_LEN],
+    reserved: [u8; GOODIX_BERLIN_HEAD_RESERVED_LEN],
+    flag: u8,
+    cfg_len: u16,
+    block_num: u8,
+    checksum: u16,
+}
+
+#[derive(Debug, Serialize)]
+#[repr(C, packed(1))]
+struct GoodixBerlinBlockDesc {
+    subcfg_id: u8,
+    reserved: u8,
+    subcfg_len: u16,
+}
+
+#[derive(Debug, Serialize)]
+#[repr(C)]
+struct GoodixBerlinBlock {
+    desc: GoodixBerlinBlockDesc,
+    #[serde(skip)]
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Serialize)]
+#[repr(C)]
+struct GoodixBerlinCfg {
+    head: GoodixBerlinCfgHead,
+    blocks: Vec<GoodixBerlinBlock>,
+}
+
+/// Either on-disk config format this crate understands, picked by
+/// [`GoodixConfig::parse`] so callers don't need to guess which IC
+/// generation produced a given `.bin`.
+#[derive(Debug, Serialize)]
+enum GoodixConfig {
+    Legacy(GoodixCfgBin),
+    Berlin(GoodixBerlinCfg),
+}
+
 #[derive(Debug)]
 enum Error {
-    InvalidSize,
-    LengthCheckFail,
-    ChecksumMismatch,
-    InvalidOffset,
+    InvalidSize { offset: usize, len: usize },
+    LengthCheckFail { expected: usize, actual: usize },
+    ChecksumMismatch { expected: u32, actual: u32 },
+    InvalidOffset { offset: usize },
+    OffsetOverflow { offset: usize },
+    ConfigTooLarge { len: usize },
+    EmptyConfig,
+    ConfigChecksumMismatch,
 }
 
-impl GoodixCfgBin {
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidSize { offset, len } => {
+                write!(f, "not enough data at offset {offset} to read {len} byte(s)")
+            }
+            Error::LengthCheckFail { expected, actual } => write!(
+                f,
+                "declared size mismatch: header implies {expected} byte(s), file is {actual} byte(s)"
+            ),
+            Error::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: header declares {expected:#x}, computed {actual:#x}"
+            ),
+            Error::InvalidOffset { offset } => write!(f, "invalid package offset {offset}"),
+            Error::OffsetOverflow { offset } => write!(
+                f,
+                "package offset {offset} does not fit the 16-bit offset table"
+            ),
+            Error::ConfigTooLarge { len } => write!(
+                f,
+                "config payload is {len} byte(s), exceeds the {GOODIX_CFG_MAX_SIZE}-byte limit"
+            ),
+            Error::EmptyConfig => write!(f, "config payload is empty"),
+            Error::ConfigChecksumMismatch => write!(
+                f,
+                "config payload's trailing checksum byte does not match its contents"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A bounds-checked cursor over a byte slice. Every read validates that
+/// `pos + size <= data.len()` before touching memory, so a truncated or
+/// hostile `.bin` yields an `Error` instead of an out-of-bounds read.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<(), Error> {
+        if pos > self.data.len() {
+            return Err(Error::InvalidOffset { offset: pos });
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        let offset = self.pos;
+        let end = self
+            .pos
+            .checked_add(n)
+            .ok_or(Error::InvalidSize { offset, len: n })?;
+        if end > self.data.len() {
+            return Err(Error::InvalidSize { offset, len: n });
+        }
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut arr = [0u8; N];
+        arr.copy_from_slice(self.read_bytes(N)?);
+        Ok(arr)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16_le(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.read_array::<2>()?))
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.read_array::<4>()?))
+    }
+}
+
+fn read_pkg_reg(r: &mut Reader) -> Result<GoodixCfgPkgReg, Error> {
+    Ok(GoodixCfgPkgReg {
+        addr: r.read_u16_le()?,
+        reserved1: r.read_u8()?,
+        reserved2: r.read_u8()?,
+    })
+}
+
+fn read_head(r: &mut Reader) -> Result<GoodixCfgBinHead, Error> {
+    Ok(GoodixCfgBinHead {
+        bin_len: r.read_u32_le()?,
+        checksum: r.read_u8()?,
+        bin_version: r.read_array::<TS_BIN_VERSION_LEN>()?,
+        pkg_num: r.read_u8()?,
+    })
+}
+
+fn read_const_info(r: &mut Reader) -> Result<GoodixCfgPkgConstInfo, Error> {
+    Ok(GoodixCfgPkgConstInfo {
+        pkg_len: r.read_u32_le()?,
+        ic_type: r.read_array::<TS_IC_TYPE_NAME_MAX_LEN>()?,
+        cfg_type: r.read_u8()?,
+        sensor_id: r.read_u8()?,
+        hw_pid: r.read_array::<TS_CFG_BLOCK_PID_LEN>()?,
+        hw_vid: r.read_array::<TS_CFG_BLOCK_VID_LEN>()?,
+        fw_mask: r.read_array::<TS_CFG_BLOCK_FW_MASK_LEN>()?,
+        fw_patch: r.read_array::<TS_CFG_BLOCK_FW_PATCH_LEN>()?,
+        x_res_offset: r.read_u16_le()?,
+        y_res_offset: r.read_u16_le()?,
+        trigger_offset: r.read_u16_le()?,
+    })
+}
+
+fn read_reg_info(r: &mut Reader) -> Result<GoodixCfgPkgRegInfo, Error> {
+    Ok(GoodixCfgPkgRegInfo {
+        cfg_send_flag: read_pkg_reg(r)?,
+        version_base: read_pkg_reg(r)?,
+        pid: read_pkg_reg(r)?,
+        vid: read_pkg_reg(r)?,
+        sensor_id: read_pkg_reg(r)?,
+        fw_mask: read_pkg_reg(r)?,
+        fw_status: read_pkg_reg(r)?,
+        cfg_addr: read_pkg_reg(r)?,
+        esd: read_pkg_reg(r)?,
+        command: read_pkg_reg(r)?,
+        coor: read_pkg_reg(r)?,
+        gesture: read_pkg_reg(r)?,
+        fw_request: read_pkg_reg(r)?,
+        proximity: read_pkg_reg(r)?,
+        reserved: r.read_array::<TS_CFG_BLOCK_RESERVED_LEN>()?,
+    })
+}
+
+fn read_berlin_head(r: &mut Reader) -> Result<GoodixBerlinCfgHead, Error> {
+    Ok(GoodixBerlinCfgHead {
+        panel_name: r.read_array::<GOODIX_BERLIN_PANEL_NAME_LEN>()?,
+        fw_pid: r.read_array::<GOODIX_BERLIN_FW_PID_LEN>()?,
+        fw_vid: r.read_array::<GOODIX_BERLIN_FW_VID_LEN>()?,
+        reserved: r.read_array::<GOODIX_BERLIN_HEAD_RESERVED_LEN>()?,
+        flag: r.read_u8()?,
+        cfg_len: r.read_u16_le()?,
+        block_num: r.read_u8()?,
+        checksum: r.read_u16_le()?,
+    })
+}
+
+fn read_berlin_block_desc(r: &mut Reader) -> Result<GoodixBerlinBlockDesc, Error> {
+    Ok(GoodixBerlinBlockDesc {
+        subcfg_id: r.read_u8()?,
+        reserved: r.read_u8()?,
+        subcfg_len: r.read_u16_le()?,
+    })
+}
+
+impl GoodixBerlinCfg {
     pub fn parse(input: &[u8]) -> Result<Self, Error> {
-        #[allow(invalid_value)]
-        let mut this: GoodixCfgBin = unsafe { MaybeUninit::zeroed().assume_init() };
+        let mut reader = Reader::new(input);
+        let head = read_berlin_head(&mut reader)?;
 
-        if input.len() < size_of::<GoodixCfgBinHead>() {
-            return Err(Error::InvalidSize);
+        let block_table_len = head.block_num as usize * GOODIX_BERLIN_BLOCK_DESC_LEN;
+        let expected_len = GOODIX_BERLIN_CFG_HEAD_LEN + block_table_len + head.cfg_len as usize;
+        if input.len() != expected_len {
+            return Err(Error::LengthCheckFail {
+                expected: expected_len,
+                actual: input.len(),
+            });
         }
 
-        unsafe {
-            copy_nonoverlapping(
-                input.as_ptr(),
-                transmute(&mut this.head),
-                size_of::<GoodixCfgBinHead>(),
-            )
-        };
+        let mut checksum: u16 = 0;
+        for (i, &byte) in input.iter().enumerate() {
+            if (GOODIX_BERLIN_CHECKSUM_OFFSET..GOODIX_BERLIN_CHECKSUM_OFFSET + GOODIX_BERLIN_CHECKSUM_LEN)
+                .contains(&i)
+            {
+                continue;
+            }
+            checksum = checksum.wrapping_add(byte as u16);
+        }
+        if checksum != head.checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: head.checksum as u32,
+                actual: checksum as u32,
+            });
+        }
 
-        if input.len() as u32 != this.head.bin_len {
-            return Err(Error::LengthCheckFail);
+        let mut descs = Vec::with_capacity(head.block_num as usize);
+        for _ in 0..head.block_num {
+            descs.push(read_berlin_block_desc(&mut reader)?);
         }
 
-        let mut checksum = 0;
+        let mut blocks = Vec::with_capacity(descs.len());
+        for desc in descs {
+            let data = reader.read_bytes(desc.subcfg_len as usize)?.to_vec();
+            blocks.push(GoodixBerlinBlock { desc, data });
+        }
 
-        for i in TS_BIN_VERSION_START_INDEX..input.len() {
-            checksum += input[i];
+        Ok(GoodixBerlinCfg { head, blocks })
+    }
+}
+
+impl GoodixConfig {
+    /// Detects which on-disk format `input` is in and parses it.
+    ///
+    /// Detection happens structurally, before committing to either parser:
+    /// the legacy head's leading `bin_len` field should equal the file's
+    /// actual length. Checking that plausibility up front (rather than
+    /// trying the legacy parser and falling back to Berlin on any failure)
+    /// means a legacy file with, say, a corrupted package checksum is
+    /// reported as exactly that, instead of being silently reinterpreted as
+    /// a malformed Berlin file.
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        if Self::looks_legacy(input) {
+            GoodixCfgBin::parse(input).map(GoodixConfig::Legacy)
+        } else {
+            GoodixBerlinCfg::parse(input).map(GoodixConfig::Berlin)
         }
+    }
 
-        if checksum != this.head.checksum {
-            return Err(Error::ChecksumMismatch);
+    fn looks_legacy(input: &[u8]) -> bool {
+        match Reader::new(input).read_u32_le() {
+            Ok(bin_len) => bin_len as usize == input.len(),
+            Err(_) => false,
         }
+    }
 
-        this.cfg_pkgs = Vec::with_capacity(this.head.pkg_num as usize);
+    /// Raw per-sensor/per-block config bytes, keyed the same way regardless
+    /// of which on-disk format produced this image.
+    pub fn ic_configs(&self) -> HashMap<u8, &[u8]> {
+        match self {
+            GoodixConfig::Legacy(bin) => bin
+                .cfg_pkgs
+                .iter()
+                .map(|pkg| (pkg.cnst_info.cfg_type, pkg.cfg.as_slice()))
+                .collect(),
+            GoodixConfig::Berlin(cfg) => cfg
+                .blocks
+                .iter()
+                .map(|block| (block.desc.subcfg_id, block.data.as_slice()))
+                .collect(),
+        }
+    }
+}
 
-        let mut offset1;
-        let mut offset2;
-        for i in 0..this.head.pkg_num as usize {
-            // This overflows intentionally???
-            offset1 = input[TS_CFG_BIN_HEAD_LEN + i * TS_CFG_OFFSET_LEN]
-                + (input[TS_CFG_BIN_HEAD_LEN + i * TS_CFG_OFFSET_LEN + 1] << 8);
+impl GoodixCfgBin {
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(input);
 
-            let mut cfg_pkg: GoodixCfgPackage = unsafe { MaybeUninit::zeroed().assume_init() };
+        let head = read_head(&mut reader)?;
+        reader.seek(TS_CFG_BIN_HEAD_LEN)?;
 
-            if i == this.head.pkg_num as usize - 1 {
-                cfg_pkg.pkg_len = input.len() as u32 - offset1 as u32;
-            } else {
-                // This too???
-                offset2 = input[TS_CFG_BIN_HEAD_LEN + i * TS_CFG_OFFSET_LEN + 2]
-                    + (input[TS_CFG_BIN_HEAD_LEN + i * TS_CFG_OFFSET_LEN + 3] << 8);
+        if input.len() as u32 != head.bin_len {
+            return Err(Error::LengthCheckFail {
+                expected: head.bin_len as usize,
+                actual: input.len(),
+            });
+        }
+
+        let mut checksum: u8 = 0;
+        for &byte in &input[TS_BIN_VERSION_START_INDEX..] {
+            checksum = checksum.wrapping_add(byte);
+        }
+
+        if checksum != head.checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: head.checksum as u32,
+                actual: checksum as u32,
+            });
+        }
+
+        let mut offsets = Vec::with_capacity(head.pkg_num as usize);
+        for _ in 0..head.pkg_num {
+            offsets.push(reader.read_u16_le()? as usize);
+        }
+
+        let mut cfg_pkgs = Vec::with_capacity(head.pkg_num as usize);
+        let mut ic_configs = HashMap::new();
+
+        for i in 0..head.pkg_num as usize {
+            let offset = offsets[i];
 
-                if offset2 <= offset1 {
-                    return Err(Error::InvalidOffset);
+            let pkg_len = if i == head.pkg_num as usize - 1 {
+                input
+                    .len()
+                    .checked_sub(offset)
+                    .ok_or(Error::InvalidOffset { offset })?
+            } else {
+                let next_offset = offsets[i + 1];
+                if next_offset <= offset {
+                    return Err(Error::InvalidOffset { offset });
                 }
+                next_offset - offset
+            };
 
-                cfg_pkg.pkg_len = (offset2 - offset1) as u32;
-            }
+            let mut pkg_reader = Reader::new(input);
+            pkg_reader.seek(offset)?;
+
+            let cnst_info = read_const_info(&mut pkg_reader)?;
+            let reg_info = read_reg_info(&mut pkg_reader)?;
 
-            unsafe {
-                copy_nonoverlapping(
-                    input.as_ptr().add(offset1 as usize),
-                    transmute(&mut cfg_pkg.cnst_info),
-                    TS_PKG_CONST_INFO_LEN,
-                );
-                copy_nonoverlapping(
-                    input
-                        .as_ptr()
-                        .add(offset1 as usize)
-                        .add(TS_PKG_CONST_INFO_LEN),
-                    transmute(&mut cfg_pkg.reg_info),
-                    TS_PKG_REG_INFO_LEN,
-                );
-                cfg_pkg.cfg = &input[offset1 as usize + TS_PKG_HEAD_LEN];
+            let cfg_len = pkg_len
+                .checked_sub(TS_PKG_HEAD_LEN)
+                .ok_or(Error::InvalidSize {
+                    offset,
+                    len: pkg_len,
+                })?;
+            // `ic_configs` stores each payload in a fixed-size buffer, so a
+            // payload that wouldn't fit has to be rejected here regardless
+            // of policy. This check runs before the payload is read, so an
+            // oversized payload is reported as the same `ConfigTooLarge`
+            // that `validate()` reports for an oversized hand-edited config,
+            // not as a short read (`InvalidSize`).
+            if cfg_len > GOODIX_CFG_MAX_SIZE {
+                return Err(Error::ConfigTooLarge { len: cfg_len });
             }
+            let cfg = pkg_reader.read_bytes(cfg_len)?.to_vec();
+            let mut data = [0u8; GOODIX_CFG_MAX_SIZE];
+            data[..cfg_len].copy_from_slice(&cfg);
 
-            // Get the ic config for this sensor ID
-            let cfg_len = cfg_pkg.pkg_len as usize - TS_PKG_CONST_INFO_LEN - TS_PKG_REG_INFO_LEN;
-            let mut ic_config_data = [0; GOODIX_CFG_MAX_SIZE];
-            unsafe { copy_nonoverlapping(cfg_pkg.cfg, ic_config_data.as_mut_ptr(), cfg_len) }
+            ic_configs.insert(
+                cnst_info.cfg_type,
+                GoodixIcConfig {
+                    len: cfg_len as i32,
+                    data,
+                },
+            );
 
-            let ic_config = GoodixIcConfig {
-                len: cfg_len as i32,
-                data: ic_config_data,
-            };
+            cfg_pkgs.push(GoodixCfgPackage {
+                cnst_info,
+                reg_info,
+                cfg,
+                pkg_len: pkg_len as u32,
+            });
+        }
+
+        Ok(GoodixCfgBin {
+            head,
+            cfg_pkgs,
+            ic_configs,
+        })
+    }
 
-            this.ic_configs
-                .insert(cfg_pkg.cnst_info.cfg_type, ic_config);
+    /// Serializes this config back into a device-loadable `.cfg.bin` image.
+    ///
+    /// `bin_len`, each package's `pkg_len`, the offset table, and the
+    /// header checksum are all recomputed from the current contents, so
+    /// editing a package's `cfg` bytes or header fields and calling this
+    /// again produces a self-consistent file.
+    ///
+    /// Fails with `Error::OffsetOverflow` rather than silently wrapping if
+    /// the cumulative size of the header, offset table, and packages so far
+    /// written pushes a package's offset past what the 16-bit offset table
+    /// can represent.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let offset_table_len = self.cfg_pkgs.len() * TS_CFG_OFFSET_LEN;
+        let mut offset = TS_CFG_BIN_HEAD_LEN + offset_table_len;
+
+        let mut offsets = Vec::with_capacity(self.cfg_pkgs.len());
+        let mut pkg_bytes = Vec::with_capacity(self.cfg_pkgs.len());
+        for pkg in &self.cfg_pkgs {
+            offsets.push(u16::try_from(offset).map_err(|_| Error::OffsetOverflow { offset })?);
+            let bytes = encode_package(pkg);
+            offset += bytes.len();
+            pkg_bytes.push(bytes);
+        }
+
+        let bin_len = offset as u32;
+
+        let mut out = Vec::with_capacity(bin_len as usize);
+        out.extend_from_slice(&bin_len.to_le_bytes());
+        out.push(0); // checksum, filled in once the rest of the image is written
+        out.extend_from_slice(&self.head.bin_version);
+        out.push(self.cfg_pkgs.len() as u8);
+        out.resize(TS_CFG_BIN_HEAD_LEN, 0);
+
+        for offset in &offsets {
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+        for bytes in &pkg_bytes {
+            out.extend_from_slice(bytes);
+        }
 
-            this.cfg_pkgs.push(cfg_pkg);
+        let mut checksum: u8 = 0;
+        for &byte in &out[TS_BIN_VERSION_START_INDEX..] {
+            checksum = checksum.wrapping_add(byte);
         }
+        out[TS_BIN_VERSION_START_INDEX - 1] = checksum;
 
-        Ok(this)
+        Ok(out)
     }
+
+    /// Validates already-parsed packages the way the Berlin driver validates
+    /// a config blob before handing it to firmware (`brl_send_config`):
+    /// reject anything that would overflow the fixed `[u8; 4096]` buffer,
+    /// anything with no payload at all, and anything whose own trailing
+    /// checksum byte (the same mod-256 scheme this format uses for the bin
+    /// header) doesn't match its contents.
+    ///
+    /// This is a separate pass from `parse`: `parse` only rejects a payload
+    /// that can't structurally fit the package table or the fixed-size
+    /// `ic_configs` buffer, while `validate` judges whether already-parsed
+    /// (or since hand-edited, e.g. before re-encoding) config data is
+    /// actually sane by firmware's standards.
+    pub fn validate(&self) -> Result<(), Error> {
+        for pkg in &self.cfg_pkgs {
+            let cfg = &pkg.cfg;
+
+            if cfg.len() > GOODIX_CFG_MAX_SIZE {
+                return Err(Error::ConfigTooLarge { len: cfg.len() });
+            }
+            if cfg.is_empty() {
+                return Err(Error::EmptyConfig);
+            }
+            // A 1-byte payload is still subject to the checksum rule: with
+            // no preceding bytes to sum, its own (only) byte must be 0.
+            let (body, trailer) = cfg.split_at(cfg.len() - 1);
+            let checksum = body.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+            if checksum != trailer[0] {
+                return Err(Error::ConfigChecksumMismatch);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_pkg_reg(out: &mut Vec<u8>, reg: &GoodixCfgPkgReg) {
+    out.extend_from_slice(&reg.addr.to_le_bytes());
+    out.push(reg.reserved1);
+    out.push(reg.reserved2);
+}
+
+fn write_const_info(out: &mut Vec<u8>, info: &GoodixCfgPkgConstInfo, pkg_len: u32) {
+    out.extend_from_slice(&pkg_len.to_le_bytes());
+    out.extend_from_slice(&info.ic_type);
+    out.push(info.cfg_type);
+    out.push(info.sensor_id);
+    out.extend_from_slice(&info.hw_pid);
+    out.extend_from_slice(&info.hw_vid);
+    out.extend_from_slice(&info.fw_mask);
+    out.extend_from_slice(&info.fw_patch);
+    out.extend_from_slice(&info.x_res_offset.to_le_bytes());
+    out.extend_from_slice(&info.y_res_offset.to_le_bytes());
+    out.extend_from_slice(&info.trigger_offset.to_le_bytes());
+}
+
+fn write_reg_info(out: &mut Vec<u8>, info: &GoodixCfgPkgRegInfo) {
+    write_pkg_reg(out, &info.cfg_send_flag);
+    write_pkg_reg(out, &info.version_base);
+    write_pkg_reg(out, &info.pid);
+    write_pkg_reg(out, &info.vid);
+    write_pkg_reg(out, &info.sensor_id);
+    write_pkg_reg(out, &info.fw_mask);
+    write_pkg_reg(out, &info.fw_status);
+    write_pkg_reg(out, &info.cfg_addr);
+    write_pkg_reg(out, &info.esd);
+    write_pkg_reg(out, &info.command);
+    write_pkg_reg(out, &info.coor);
+    write_pkg_reg(out, &info.gesture);
+    write_pkg_reg(out, &info.fw_request);
+    write_pkg_reg(out, &info.proximity);
+    out.extend_from_slice(&info.reserved);
 }
 
+fn encode_package(pkg: &GoodixCfgPackage) -> Vec<u8> {
+    let pkg_len = (TS_PKG_HEAD_LEN + pkg.cfg.len()) as u32;
+
+    let mut out = Vec::with_capacity(pkg_len as usize);
+    write_const_info(&mut out, &pkg.cnst_info, pkg_len);
+    write_reg_info(&mut out, &pkg.reg_info);
+    out.extend_from_slice(&pkg.cfg);
+    out
+}
+
+const USAGE: &str =
+    "usage: goodix-cfg-bin <cfg.bin> [dump|list|extract <cfg_type> <outfile>|encode <outfile>]";
+
 fn main() {
-    let cfg_bin_file = env::args().nth(1).expect("No cfg bin file provided");
-    let contents = fs::read(cfg_bin_file).expect("Failed to read cfg bin file");
-    let cfg_bin = GoodixCfgBin::parse(&contents).unwrap();
+    if let Err(err) = run(env::args().skip(1)) {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+fn run(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn std::error::Error>> {
+    let cfg_bin_file = args.next().ok_or(USAGE)?;
+    let contents = fs::read(&cfg_bin_file)?;
+    let cfg = GoodixConfig::parse(&contents)?;
+
+    match args.next().as_deref() {
+        None | Some("dump") => println!("{}", serde_json::to_string_pretty(&cfg)?),
+
+        Some("list") => {
+            println!("{:<10} {:<10} {:<10}", "cfg_type", "sensor_id", "pkg_len");
+            match &cfg {
+                GoodixConfig::Legacy(bin) => {
+                    for pkg in &bin.cfg_pkgs {
+                        let cfg_type = pkg.cnst_info.cfg_type;
+                        let sensor_id = pkg.cnst_info.sensor_id;
+                        let pkg_len = pkg.pkg_len;
+                        println!("{cfg_type:<10} {sensor_id:<10} {pkg_len:<10}");
+                    }
+                }
+                GoodixConfig::Berlin(cfg) => {
+                    for block in &cfg.blocks {
+                        let subcfg_id = block.desc.subcfg_id;
+                        println!("{:<10} {:<10} {:<10}", subcfg_id, "-", block.data.len());
+                    }
+                }
+            }
+        }
+
+        Some("extract") => {
+            let cfg_type: u8 = args.next().ok_or(USAGE)?.parse()?;
+            let out_file = args.next().ok_or(USAGE)?;
+
+            // dump/list only read already-parsed metadata, but extracting
+            // raw config bytes is exactly what validate() exists to gate.
+            if let GoodixConfig::Legacy(bin) = &cfg {
+                bin.validate()?;
+            }
+
+            let ic_configs = cfg.ic_configs();
+            let data = *ic_configs
+                .get(&cfg_type)
+                .ok_or_else(|| format!("no config with cfg_type {cfg_type} in this image"))?;
+            fs::write(out_file, data)?;
+        }
+
+        Some("encode") => {
+            let out_file = args.next().ok_or(USAGE)?;
+            match cfg {
+                GoodixConfig::Legacy(bin) => {
+                    bin.validate()?;
+                    fs::write(out_file, bin.to_bytes()?)?;
+                }
+                GoodixConfig::Berlin(_) => {
+                    return Err("re-encoding Berlin-format configs is not yet supported".into())
+                }
+            }
+        }
+
+        Some(other) => return Err(format!("unknown subcommand {other:?}\n{USAGE}").into()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_bin() -> GoodixCfgBin {
+        let cfg = vec![0xAA, 0xBB, 0xCC];
+
+        let cnst_info = GoodixCfgPkgConstInfo {
+            pkg_len: 0,
+            ic_type: [0u8; TS_IC_TYPE_NAME_MAX_LEN],
+            cfg_type: 1,
+            sensor_id: 2,
+            hw_pid: [0u8; TS_CFG_BLOCK_PID_LEN],
+            hw_vid: [0u8; TS_CFG_BLOCK_VID_LEN],
+            fw_mask: [0u8; TS_CFG_BLOCK_FW_MASK_LEN],
+            fw_patch: [0u8; TS_CFG_BLOCK_FW_PATCH_LEN],
+            x_res_offset: 10,
+            y_res_offset: 20,
+            trigger_offset: 30,
+        };
+
+        let reg = GoodixCfgPkgReg {
+            addr: 0,
+            reserved1: 0,
+            reserved2: 0,
+        };
+        let reg_info = GoodixCfgPkgRegInfo {
+            cfg_send_flag: reg,
+            version_base: reg,
+            pid: reg,
+            vid: reg,
+            sensor_id: reg,
+            fw_mask: reg,
+            fw_status: reg,
+            cfg_addr: reg,
+            esd: reg,
+            command: reg,
+            coor: reg,
+            gesture: reg,
+            fw_request: reg,
+            proximity: reg,
+            reserved: [0u8; TS_CFG_BLOCK_RESERVED_LEN],
+        };
+
+        let mut data = [0u8; GOODIX_CFG_MAX_SIZE];
+        data[..cfg.len()].copy_from_slice(&cfg);
+        let mut ic_configs = HashMap::new();
+        ic_configs.insert(
+            cnst_info.cfg_type,
+            GoodixIcConfig {
+                len: cfg.len() as i32,
+                data,
+            },
+        );
+
+        GoodixCfgBin {
+            head: GoodixCfgBinHead {
+                bin_len: 0,
+                checksum: 0,
+                bin_version: [1, 0, 0, 0],
+                pkg_num: 1,
+            },
+            cfg_pkgs: vec![GoodixCfgPackage {
+                cnst_info,
+                reg_info,
+                cfg,
+                pkg_len: 0,
+            }],
+            ic_configs,
+        }
+    }
+
+    #[test]
+    fn to_bytes_round_trips_through_parse() {
+        let bytes = sample_bin().to_bytes().expect("sample bin fits a u16 offset table");
+
+        let parsed = GoodixCfgBin::parse(&bytes).expect("freshly encoded bin should parse");
+        let re_encoded = parsed.to_bytes().expect("re-encoding a parsed bin should also fit");
+
+        assert_eq!(bytes, re_encoded);
+    }
+
+    #[test]
+    fn to_bytes_rejects_offset_table_overflow() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0u8; GOODIX_CFG_MAX_SIZE];
+        // Pad out with packages until the next package's offset can no
+        // longer fit in a u16.
+        for _ in 0..16 {
+            bin.cfg_pkgs.push(bin.cfg_pkgs[0].clone());
+        }
+        bin.head.pkg_num = bin.cfg_pkgs.len() as u8;
+
+        assert!(matches!(bin.to_bytes(), Err(Error::OffsetOverflow { .. })));
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0x03]; // trailing checksum of 0x01 + 0x02
+
+        assert!(bin.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_checksum_mismatch() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0xff];
+
+        assert!(matches!(bin.validate(), Err(Error::ConfigChecksumMismatch)));
+    }
+
+    #[test]
+    fn validate_rejects_empty_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![];
+
+        assert!(matches!(bin.validate(), Err(Error::EmptyConfig)));
+    }
+
+    #[test]
+    fn validate_rejects_nonzero_single_byte_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01]; // no preceding bytes, so the checksum must be 0
+
+        assert!(matches!(bin.validate(), Err(Error::ConfigChecksumMismatch)));
+    }
 
-    println!("{}", serde_json::to_string_pretty(&cfg_bin).unwrap());
+    #[test]
+    fn validate_accepts_zero_single_byte_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x00];
+
+        assert!(bin.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_oversized_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0u8; GOODIX_CFG_MAX_SIZE + 1];
+
+        assert!(matches!(bin.validate(), Err(Error::ConfigTooLarge { .. })));
+    }
+
+    #[test]
+    fn parse_rejects_oversized_package() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0u8; GOODIX_CFG_MAX_SIZE + 1];
+        let bytes = bin.to_bytes().expect("encoding an oversized package still fits a u16 offset");
+
+        assert!(matches!(
+            GoodixCfgBin::parse(&bytes),
+            Err(Error::ConfigTooLarge { len }) if len == GOODIX_CFG_MAX_SIZE + 1
+        ));
+    }
+
+    fn sample_berlin_bytes() -> Vec<u8> {
+        let block_data = vec![0xAAu8, 0xBB, 0xCC, 0xDD];
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&[0u8; GOODIX_BERLIN_PANEL_NAME_LEN]);
+        out.extend_from_slice(&[0u8; GOODIX_BERLIN_FW_PID_LEN]);
+        out.extend_from_slice(&[0u8; GOODIX_BERLIN_FW_VID_LEN]);
+        out.extend_from_slice(&[0u8; GOODIX_BERLIN_HEAD_RESERVED_LEN]);
+        out.push(0); // flag
+        out.extend_from_slice(&(block_data.len() as u16).to_le_bytes()); // cfg_len
+        out.push(1); // block_num
+        out.extend_from_slice(&0u16.to_le_bytes()); // checksum, filled in below
+
+        out.push(7); // subcfg_id
+        out.push(0); // reserved
+        out.extend_from_slice(&(block_data.len() as u16).to_le_bytes());
+        out.extend_from_slice(&block_data);
+
+        let mut checksum: u16 = 0;
+        for (i, &byte) in out.iter().enumerate() {
+            if (GOODIX_BERLIN_CHECKSUM_OFFSET..GOODIX_BERLIN_CHECKSUM_OFFSET + GOODIX_BERLIN_CHECKSUM_LEN)
+                .contains(&i)
+            {
+                continue;
+            }
+            checksum = checksum.wrapping_add(byte as u16);
+        }
+        out[GOODIX_BERLIN_CHECKSUM_OFFSET..GOODIX_BERLIN_CHECKSUM_OFFSET + GOODIX_BERLIN_CHECKSUM_LEN]
+            .copy_from_slice(&checksum.to_le_bytes());
+
+        out
+    }
+
+    #[test]
+    fn parses_berlin_format_configs() {
+        let bytes = sample_berlin_bytes();
+
+        let cfg = GoodixBerlinCfg::parse(&bytes).expect("well-formed berlin cfg should parse");
+        assert_eq!(cfg.blocks.len(), 1);
+        assert_eq!(cfg.blocks[0].data, vec![0xAA, 0xBB, 0xCC, 0xDD]);
+
+        match GoodixConfig::parse(&bytes).expect("unified parser should detect berlin format") {
+            GoodixConfig::Berlin(_) => {}
+            GoodixConfig::Legacy(_) => panic!("expected berlin format to be detected"),
+        }
+    }
+
+    #[test]
+    fn unified_parser_still_detects_legacy_format() {
+        let bytes = sample_bin().to_bytes().expect("sample bin fits a u16 offset table");
+
+        match GoodixConfig::parse(&bytes).expect("legacy cfg should parse") {
+            GoodixConfig::Legacy(_) => {}
+            GoodixConfig::Berlin(_) => panic!("expected legacy format to be detected"),
+        }
+    }
+
+    /// Writes `contents` to a fresh file under the OS temp dir and returns
+    /// its path; `tag` only needs to be unique per-test, since each call
+    /// also mixes in the PID and an atomic counter to stay collision-free
+    /// across concurrently-running tests.
+    fn write_temp_file(tag: &str, contents: &[u8]) -> std::path::PathBuf {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("goodix-cfg-bin-test-{}-{tag}-{n}", process::id()));
+        fs::write(&path, contents).expect("failed to write temp file");
+        path
+    }
+
+    fn run_args(args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        run(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn run_dump_prints_json_for_default_and_explicit_subcommand() {
+        let bytes = sample_bin().to_bytes().expect("sample bin fits a u16 offset table");
+        let path = write_temp_file("dump", &bytes);
+
+        assert!(run_args(&[path.to_str().unwrap()]).is_ok());
+        assert!(run_args(&[path.to_str().unwrap(), "dump"]).is_ok());
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn run_list_succeeds_without_validating() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0xff]; // fails validate()'s checksum heuristic
+        let bytes = bin.to_bytes().expect("sample bin fits a u16 offset table");
+        let path = write_temp_file("list", &bytes);
+
+        assert!(run_args(&[path.to_str().unwrap(), "list"]).is_ok());
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn run_extract_writes_the_requested_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0x03]; // passes validate()'s checksum check
+        let bytes = bin.to_bytes().expect("sample bin fits a u16 offset table");
+        let in_path = write_temp_file("extract-in", &bytes);
+        let out_path = std::env::temp_dir().join(format!("goodix-cfg-bin-test-{}-extract-out", process::id()));
+
+        let result = run_args(&[
+            in_path.to_str().unwrap(),
+            "extract",
+            "1",
+            out_path.to_str().unwrap(),
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(fs::read(&out_path).unwrap(), vec![0x01, 0x02, 0x03]);
+
+        fs::remove_file(in_path).ok();
+        fs::remove_file(out_path).ok();
+    }
+
+    #[test]
+    fn run_extract_rejects_config_that_fails_validate() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0xff]; // fails validate()'s checksum heuristic
+        let bytes = bin.to_bytes().expect("sample bin fits a u16 offset table");
+        let in_path = write_temp_file("extract-invalid-in", &bytes);
+        let out_path = std::env::temp_dir().join(format!(
+            "goodix-cfg-bin-test-{}-extract-invalid-out",
+            process::id()
+        ));
+
+        let result = run_args(&[
+            in_path.to_str().unwrap(),
+            "extract",
+            "1",
+            out_path.to_str().unwrap(),
+        ]);
+        assert!(result.is_err());
+
+        fs::remove_file(in_path).ok();
+    }
+
+    #[test]
+    fn run_encode_round_trips_a_valid_config() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0x01, 0x02, 0x03]; // passes validate()'s checksum check
+        let bytes = bin.to_bytes().expect("sample bin fits a u16 offset table");
+        let in_path = write_temp_file("encode-in", &bytes);
+        let out_path = std::env::temp_dir().join(format!("goodix-cfg-bin-test-{}-encode-out", process::id()));
+
+        let result = run_args(&[
+            in_path.to_str().unwrap(),
+            "encode",
+            out_path.to_str().unwrap(),
+        ]);
+        assert!(result.is_ok());
+        assert_eq!(fs::read(&out_path).unwrap(), bytes);
+
+        fs::remove_file(in_path).ok();
+        fs::remove_file(out_path).ok();
+    }
+
+    #[test]
+    fn run_rejects_unknown_subcommand() {
+        let bytes = sample_bin().to_bytes().expect("sample bin fits a u16 offset table");
+        let path = write_temp_file("bogus", &bytes);
+
+        let err = run_args(&[path.to_str().unwrap(), "bogus"])
+            .expect_err("unknown subcommand should be rejected");
+        assert!(err.to_string().contains("unknown subcommand"));
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn run_reports_corrupt_input_via_display_not_debug() {
+        let mut bin = sample_bin();
+        bin.cfg_pkgs[0].cfg = vec![0xAA, 0xBB, 0xCC, 0xDD];
+        let mut bytes = bin.to_bytes().expect("sample bin fits a u16 offset table");
+        let checksum_byte = bytes.len() - 1;
+        bytes[checksum_byte] ^= 0xFF;
+        let path = write_temp_file("corrupt", &bytes);
+
+        let err = run_args(&[path.to_str().unwrap()]).expect_err("corrupt bin should fail to parse");
+        // The point of giving `Error` a `Display` impl was a message like
+        // this, not `main`'s default `Termination` reporting via `Debug`
+        // (e.g. `ChecksumMismatch { expected: .., actual: .. }`).
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        fs::remove_file(path).ok();
+    }
 }